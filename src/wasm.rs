@@ -4,7 +4,6 @@ use crate::errors::{self, ErrorKind};
 use crate::jwt::Claims;
 use crate::jwt::Token;
 use crate::Result;
-use chrono::Duration;
 use data_encoding::HEXUPPER;
 use nkeys::KeyPair;
 use parity_wasm::elements::CustomSection;
@@ -14,10 +13,245 @@ use parity_wasm::{
     serialize,
 };
 use ring::digest::{Context, Digest, SHA256};
+use std::collections::HashSet;
 use std::io::Read;
 use std::time::{SystemTime, UNIX_EPOCH};
 const SECS_PER_DAY: u64 = 86400;
 
+/// Version of the module canonicalization used when computing `module_hash`. Recorded in the
+/// claims (`canon_version`) at embed time so extraction can pick the matching scheme. Bumping this
+/// changes the canonical byte stream; a future `CANON_VERSION` coexists with older tokens because
+/// each token declares the version it was signed under.
+const CANON_VERSION: u32 = 1;
+
+/// The pre-versioning scheme: a plain SHA-256 over the module with only the exact `jwt` section
+/// cleared. Tokens without a `canon_version` claim are verified under this version.
+const LEGACY_CANON_VERSION: u32 = 0;
+
+/// A set of rules applied to a token's time window and capabilities while it is being
+/// extracted from a module. Modeled after the `Validation` struct in `jsonwebtoken` 7.0:
+/// callers construct one, flip on the checks they care about, and hand it to
+/// [`extract_claims_with_validation`]. The default checks the `expires`/`not_before` window
+/// (only when the token actually carries those timestamps); [`Validation::lenient`] checks nothing.
+#[derive(Debug, Clone)]
+pub struct Validation {
+    /// Whether to reject the token when its `expires` timestamp is in the past.
+    pub validate_exp: bool,
+    /// Whether to reject the token when its `not_before` timestamp is in the future.
+    pub validate_nbf: bool,
+    /// Seconds of clock-skew tolerance applied on either side of the time window.
+    pub leeway: u64,
+    /// When present, extraction only succeeds if at least one of these capabilities
+    /// appears in the token's `caps` set ("any-of-these" membership).
+    pub required_caps: Option<HashSet<String>>,
+    /// When present, extraction only succeeds if at least one of these tags appears in the
+    /// token's `tags` set ("any-of-these" membership). Both `required_caps` and `required_tags`
+    /// must be satisfied when both are supplied. (The request's title also mentions an audience
+    /// check; the wascap `Claims` schema carries no audience claim, so that is intentionally out
+    /// of scope here — `subject`/`issuer` already bind the token to a module and account.)
+    pub required_tags: Option<HashSet<String>>,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Validation {
+            validate_exp: true,
+            validate_nbf: true,
+            leeway: 0,
+            required_caps: None,
+            required_tags: None,
+        }
+    }
+}
+
+impl Validation {
+    /// Creates a validation that checks the expiration and not-before window with no
+    /// clock-skew tolerance and no capability requirement.
+    pub fn new() -> Self {
+        Validation::default()
+    }
+
+    /// Creates a validation that performs no time-window or capability checks, matching the
+    /// historical behavior of [`extract_claims`].
+    pub fn lenient() -> Self {
+        Validation {
+            validate_exp: false,
+            validate_nbf: false,
+            leeway: 0,
+            required_caps: None,
+            required_tags: None,
+        }
+    }
+
+    /// Requires that the token advertise at least one of the supplied capabilities.
+    pub fn with_required_caps(mut self, caps: impl IntoIterator<Item = String>) -> Self {
+        self.required_caps = Some(caps.into_iter().collect());
+        self
+    }
+
+    /// Requires that the token advertise at least one of the supplied tags.
+    pub fn with_required_tags(mut self, tags: impl IntoIterator<Item = String>) -> Self {
+        self.required_tags = Some(tags.into_iter().collect());
+        self
+    }
+}
+
+/// A [W3C Verifiable Credential](https://www.w3.org/TR/vc-data-model/) as carried in the VC-JWT
+/// encoding. When present it is stored under a `vc` claim inside the module's signed JWT, so the
+/// credential rides alongside the native wascap [`Claims`] and inherits the same
+/// `module_hash`/`subject`/`issuer` binding to the bytecode. Ecosystems that already consume VCs
+/// can then verify and present a module's provenance in a standard format.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct VerifiableCredential {
+    /// The JSON-LD contexts (`@context`) the credential is interpreted under.
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    /// The credential `type` set, e.g. `["VerifiableCredential", ...]`.
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    /// The entity that issued the credential.
+    pub issuer: String,
+    /// The claims made about the subject (arbitrary JSON per the VC data model).
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: serde_json::Value,
+}
+
+/// The JSON-LD context every Verifiable Credential must declare first.
+const VC_BASE_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+impl VerifiableCredential {
+    /// Builds a minimal Verifiable Credential that binds `credential_subject` to `issuer`,
+    /// prepending the base VC context and `VerifiableCredential` type expected by consumers.
+    pub fn new(
+        issuer: impl Into<String>,
+        extra_types: impl IntoIterator<Item = String>,
+        credential_subject: serde_json::Value,
+    ) -> Self {
+        let mut types = vec!["VerifiableCredential".to_string()];
+        types.extend(extra_types);
+        VerifiableCredential {
+            context: vec![VC_BASE_CONTEXT.to_string()],
+            types,
+            issuer: issuer.into(),
+            credential_subject,
+        }
+    }
+}
+
+/// Builds a [`Token`] from a verified JWT and its claims, surfacing any Verifiable Credential
+/// carried in the `vc` claim on the token's `vc` field. Because the credential lives inside the
+/// signed payload, it inherits the signature and `module_hash` binding already verified during
+/// extraction.
+fn build_token(jwt: String, claims: Claims) -> Result<Token> {
+    let vc = match &claims.vc {
+        Some(value) => Some(serde_json::from_value(value.clone())?),
+        None => None,
+    };
+    Ok(Token { jwt, claims, vc })
+}
+
+/// A source of signing material for the embedding paths. Following the `EncodingKey` split that
+/// `jsonwebtoken` introduced, this decouples the signing logic from the raw seed so integrations
+/// can sign from an in-process `KeyPair`, a seed string, or an external callback (HSM, remote KMS)
+/// without the embedding code ever holding the key.
+pub trait ClaimsSigner {
+    /// Produces the signed JWT for the supplied claims.
+    fn sign(&self, claims: &Claims) -> Result<String>;
+    /// The issuer public key that counterparties verify the resulting token against.
+    fn public_key(&self) -> String;
+}
+
+/// A source of verification material for the extraction paths. The default [`IssuerVerifier`]
+/// verifies purely from the issuer public key embedded in the JWT, so callers no longer need to
+/// reconstruct a full `KeyPair` just to check a signature.
+pub trait ClaimsVerifier {
+    /// Decodes and verifies the JWT, returning its claims on success.
+    fn verify(&self, jwt: &str) -> Result<Claims>;
+}
+
+impl ClaimsSigner for KeyPair {
+    fn sign(&self, claims: &Claims) -> Result<String> {
+        claims.encode(self)
+    }
+
+    fn public_key(&self) -> String {
+        KeyPair::public_key(self)
+    }
+}
+
+/// A [`ClaimsSigner`] backed by an opaque callback, for HSM or remote-KMS workflows where the
+/// private key never enters the process. The callback owns the JWT assembly and signing; this
+/// type only carries the issuer public key and forwards to it.
+pub struct CallbackSigner<F>
+where
+    F: Fn(&Claims) -> Result<String>,
+{
+    public_key: String,
+    sign_fn: F,
+}
+
+impl<F> CallbackSigner<F>
+where
+    F: Fn(&Claims) -> Result<String>,
+{
+    /// Creates a signer from an issuer public key and a closure that produces the signed JWT.
+    pub fn new(public_key: impl Into<String>, sign_fn: F) -> Self {
+        CallbackSigner {
+            public_key: public_key.into(),
+            sign_fn,
+        }
+    }
+}
+
+impl<F> ClaimsSigner for CallbackSigner<F>
+where
+    F: Fn(&Claims) -> Result<String>,
+{
+    fn sign(&self, claims: &Claims) -> Result<String> {
+        (self.sign_fn)(claims)
+    }
+
+    fn public_key(&self) -> String {
+        self.public_key.clone()
+    }
+}
+
+/// The default verifier: validates the token's signature against the issuer public key carried
+/// inside the JWT itself, requiring no externally supplied key.
+pub struct IssuerVerifier;
+
+impl ClaimsVerifier for IssuerVerifier {
+    fn verify(&self, jwt: &str) -> Result<Claims> {
+        Claims::decode(jwt)
+    }
+}
+
+/// A verify-only verifier constructed from an exported public key string. After the embedded
+/// signature checks out, the issuer is pinned to the expected key, rejecting tokens signed by
+/// anyone else.
+pub struct PublicKeyVerifier {
+    public_key: String,
+}
+
+impl PublicKeyVerifier {
+    /// Creates a verifier that only accepts tokens issued by `public_key`.
+    pub fn new(public_key: impl Into<String>) -> Self {
+        PublicKeyVerifier {
+            public_key: public_key.into(),
+        }
+    }
+}
+
+impl ClaimsVerifier for PublicKeyVerifier {
+    fn verify(&self, jwt: &str) -> Result<Claims> {
+        let claims = Claims::decode(jwt)?;
+        if claims.issuer != self.public_key {
+            return Err(errors::new(ErrorKind::InvalidSignature));
+        }
+        Ok(claims)
+    }
+}
+
 /// Extracts a set of claims from the raw bytes of a WebAssembly module. In the case where no
 /// JWT is discovered in the module, this function returns `None`.
 /// If there is a token in the file with a valid hash, then you will get a `Token` back
@@ -28,27 +262,127 @@ const SECS_PER_DAY: u64 = 86400;
 /// forms JWT, or the `module_hash` claim inside the decoded JWT does not match the hash
 /// of the file.
 pub fn extract_claims(contents: impl AsRef<[u8]>) -> Result<Option<Token>> {
+    Ok(extract_all_claims_with_validation(contents, &Validation::lenient())?
+        .into_iter()
+        .next())
+}
+
+/// Extracts every token stacked onto a WebAssembly module, one per `jwt`-family custom section
+/// (`jwt`, `jwt.1`, ...). This is the multi-signature counterpart to [`extract_claims`]: a single
+/// artifact can carry an account key's attestation plus any number of independent counter-signatures
+/// from auditors or notaries. Every token's `module_hash` is validated against the module with *all*
+/// `jwt`-family sections stripped, so each signature is checked against the same stable hash. A
+/// module with no tokens yields an empty vector.
+pub fn extract_all_claims(contents: impl AsRef<[u8]>) -> Result<Vec<Token>> {
+    extract_all_claims_with_validation(contents, &Validation::lenient())
+}
+
+/// Like [`extract_all_claims`], but applies `validation` to each extracted token.
+pub fn extract_all_claims_with_validation(
+    contents: impl AsRef<[u8]>,
+    validation: &Validation,
+) -> Result<Vec<Token>> {
+    extract_all_claims_inner(contents, validation, &IssuerVerifier)
+}
+
+/// Verifies every `jwt`-family token on the module using a caller-supplied [`ClaimsVerifier`] and
+/// returns the first one, letting integrations plug in a pinned public key or a custom signature
+/// check in place of the default issuer-embedded verification. If any stacked token fails
+/// verification, the whole call fails.
+pub fn extract_claims_with_verifier(
+    contents: impl AsRef<[u8]>,
+    verifier: &dyn ClaimsVerifier,
+) -> Result<Option<Token>> {
+    Ok(extract_all_claims_inner(contents, &Validation::lenient(), verifier)?
+        .into_iter()
+        .next())
+}
+
+fn extract_all_claims_inner(
+    contents: impl AsRef<[u8]>,
+    validation: &Validation,
+    verifier: &dyn ClaimsVerifier,
+) -> Result<Vec<Token>> {
     let module: Module = deserialize_buffer(contents.as_ref())?;
 
-    let sections: Vec<&CustomSection> = module
+    let mut sections: Vec<&CustomSection> = module
         .custom_sections()
-        .filter(|sect| sect.name() == "jwt")
+        .filter(|sect| is_jwt_section(sect.name()))
         .collect();
+    // Order tokens deterministically: `jwt` first, then `jwt.1`, `jwt.2`, ...
+    sections.sort_by_key(|sect| jwt_section_index(sect.name()));
 
-    if sections.len() == 0 {
-        Ok(None)
-    } else {
-        let jwt = String::from_utf8(sections[0].payload().to_vec())?;
-        let claims = Claims::decode(&jwt)?;
-        let hash = compute_hash_without_jwt(module)?;
+    let mut tokens = Vec::with_capacity(sections.len());
+    for sect in sections {
+        let jwt = String::from_utf8(sect.payload().to_vec())?;
+        let claims = verifier.verify(&jwt)?;
+        if !hash_matches(&module, &claims)? {
+            return Err(errors::new(ErrorKind::InvalidModuleHash));
+        }
+        validate_claims(&claims, validation)?;
+        tokens.push(build_token(jwt, claims)?);
+    }
+    Ok(tokens)
+}
 
-        /* TODO: FIX MODULE HASHING */
-        if hash != claims.module_hash {
-            Err(errors::new(ErrorKind::InvalidModuleHash))
-        } else {
-            Ok(Some(Token { jwt, claims }))
+/// Extracts a set of claims from the raw bytes of a WebAssembly module, enforcing the rules
+/// described by the supplied [`Validation`]. As with [`extract_claims`], a module containing
+/// no JWT yields `None`, and a token whose `module_hash` does not match the file is rejected
+/// with [`ErrorKind::InvalidModuleHash`].
+///
+/// # Errors
+/// In addition to the errors returned by [`extract_claims`], this function returns
+/// [`ErrorKind::TokenExpired`] when `validate_exp` is set and the token's window has closed,
+/// [`ErrorKind::TokenNotValidYet`] when `validate_nbf` is set and the window has not yet
+/// opened, and [`ErrorKind::MissingCapability`] when a required-capabilities set is supplied
+/// and the token advertises none of its members.
+pub fn extract_claims_with_validation(
+    contents: impl AsRef<[u8]>,
+    validation: &Validation,
+) -> Result<Option<Token>> {
+    Ok(extract_all_claims_with_validation(contents, validation)?
+        .into_iter()
+        .next())
+}
+
+/// Applies a [`Validation`]'s time-window, capability, and tag rules to a decoded set of claims.
+fn validate_claims(claims: &Claims, validation: &Validation) -> Result<()> {
+    let now = since_the_epoch().as_secs();
+    if validation.validate_exp {
+        if let Some(exp) = claims.expires {
+            if exp < now.saturating_sub(validation.leeway) {
+                return Err(errors::new(ErrorKind::TokenExpired));
+            }
+        }
+    }
+    if validation.validate_nbf {
+        if let Some(nbf) = claims.not_before {
+            if nbf > now.saturating_add(validation.leeway) {
+                return Err(errors::new(ErrorKind::TokenNotValidYet));
+            }
+        }
+    }
+    if let Some(ref required) = validation.required_caps {
+        let satisfied = claims
+            .caps
+            .as_ref()
+            .map(|caps| caps.iter().any(|c| required.contains(c)))
+            .unwrap_or(false);
+        if !satisfied {
+            return Err(errors::new(ErrorKind::MissingCapability));
+        }
+    }
+    if let Some(ref required) = validation.required_tags {
+        let satisfied = claims
+            .tags
+            .as_ref()
+            .map(|tags| tags.iter().any(|t| required.contains(t)))
+            .unwrap_or(false);
+        if !satisfied {
+            return Err(errors::new(ErrorKind::MissingTag));
         }
     }
+    Ok(())
 }
 
 /// This function will embed a set of claims inside the bytecode of a WebAssembly module. The claims
@@ -58,14 +392,24 @@ pub fn extract_claims(contents: impl AsRef<[u8]>) -> Result<Option<Token>> {
 /// parsers or interpreters. Returns a vector of bytes representing the new WebAssembly module which can
 /// be saved to a `.wasm` file
 pub fn embed_claims(orig_bytecode: &[u8], claims: &Claims, kp: &KeyPair) -> Result<Vec<u8>> {
+    embed_claims_with_signer(orig_bytecode, claims, kp)
+}
+
+/// The signer-pluggable form of [`embed_claims`]: the claims are signed by any [`ClaimsSigner`]
+/// — an in-process `KeyPair`, or a [`CallbackSigner`] backed by an HSM or remote KMS — so the
+/// embedding logic no longer requires a raw seed in memory.
+pub fn embed_claims_with_signer(
+    orig_bytecode: &[u8],
+    claims: &Claims,
+    signer: &dyn ClaimsSigner,
+) -> Result<Vec<u8>> {
     let module: Module = deserialize_buffer(orig_bytecode)?;
-    let cleanbytes = serialize(module)?;
 
-    let digest = sha256_digest(cleanbytes.as_slice())?;
     let mut claims = (*claims).clone();
-    claims.module_hash = HEXUPPER.encode(digest.as_ref());
+    claims.module_hash = compute_hash_without_jwt(module)?;
+    claims.canon_version = Some(CANON_VERSION);
 
-    let encoded = claims.encode(&kp)?;
+    let encoded = signer.sign(&claims)?;
     let encvec = encoded.as_bytes().to_vec();
     let mut m: Module = deserialize_buffer(orig_bytecode)?;
     m.set_custom_section("jwt", encvec);
@@ -75,6 +419,128 @@ pub fn embed_claims(orig_bytecode: &[u8], claims: &Claims, kp: &KeyPair) -> Resu
     Ok(buf)
 }
 
+/// Embeds a set of claims that carries a W3C [`VerifiableCredential`] under the `vc` claim of the
+/// signed JWT. The credential is serialized into the claims before signing, so it is bound to the
+/// same `module_hash`/`subject`/`issuer` as the native claims and is surfaced on the `vc` field of
+/// the [`Token`] returned by [`extract_claims`].
+pub fn embed_claims_with_credential(
+    orig_bytecode: &[u8],
+    claims: &Claims,
+    credential: &VerifiableCredential,
+    kp: &KeyPair,
+) -> Result<Vec<u8>> {
+    let mut claims = (*claims).clone();
+    claims.vc = Some(serde_json::to_value(credential)?);
+    embed_claims_with_signer(orig_bytecode, &claims, kp)
+}
+
+/// Appends an additional signer's claims to a module that already carries one or more tokens,
+/// without disturbing the existing signatures. Each attestation is stored in its own
+/// `jwt`-family custom section (`jwt`, `jwt.1`, `jwt.2`, ...), so an account key can sign and an
+/// auditor or notary can later counter-sign the very same artifact. The new token's `module_hash`
+/// is computed over the module with all `jwt`-family sections stripped, matching the stable hash
+/// every other token on the module is verified against.
+pub fn embed_additional_claims(
+    bytecode: &[u8],
+    claims: &Claims,
+    kp: &KeyPair,
+) -> Result<Vec<u8>> {
+    let module: Module = deserialize_buffer(bytecode)?;
+    let hash = compute_hash_without_jwt(module.clone())?;
+
+    let mut claims = (*claims).clone();
+    claims.module_hash = hash;
+    claims.canon_version = Some(CANON_VERSION);
+    let encoded = claims.encode(&kp)?;
+
+    let section = next_jwt_section_name(&module);
+    let mut m = module;
+    m.set_custom_section(section, encoded.as_bytes().to_vec());
+    let mut buf = Vec::new();
+    m.serialize(&mut buf)?;
+
+    Ok(buf)
+}
+
+/// Finds the next unused `jwt`-family section name for the given module: `jwt` if none exists yet,
+/// otherwise the lowest `jwt.N` (N >= 1) not already present.
+fn next_jwt_section_name(module: &Module) -> String {
+    let existing: HashSet<String> = module
+        .custom_sections()
+        .map(|sect| sect.name().to_string())
+        .filter(|name| is_jwt_section(name))
+        .collect();
+    if !existing.contains("jwt") {
+        return "jwt".to_string();
+    }
+    let mut n = 1u64;
+    loop {
+        let candidate = format!("jwt.{}", n);
+        if !existing.contains(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Signs a set of claims over a WebAssembly module without modifying the module. Instead of
+/// writing a `jwt` custom section, this returns the untouched original bytes alongside the
+/// freshly signed JWT so the two can be shipped as separate `.wasm` and `.jwt` artifacts. This
+/// keeps the module as small as possible for constrained hosts and lets the signature be rotated
+/// without rewriting the bytecode. The `module_hash` is computed over the clean serialized module
+/// exactly as in [`embed_claims`], so a detached token is interchangeable with an embedded one.
+pub fn embed_claims_detached(orig_bytecode: &[u8], claims: &Claims, kp: &KeyPair) -> Result<String> {
+    let module: Module = deserialize_buffer(orig_bytecode)?;
+
+    let mut claims = (*claims).clone();
+    claims.module_hash = compute_hash_without_jwt(module)?;
+    claims.canon_version = Some(CANON_VERSION);
+
+    claims.encode(&kp)
+}
+
+/// Extracts and verifies claims from a detached (sidecar) JWT against the raw module bytes it
+/// was signed over. Unlike [`extract_claims`], the module carries no `jwt` section, so the hash
+/// is recomputed directly over the supplied bytes under the canonicalization version the token
+/// declares (which simply finds no `jwt` section to strip) and compared against the token's
+/// `module_hash`.
+///
+/// # Errors
+/// Returns [`ErrorKind::InvalidModuleHash`] if the token was not signed over these exact bytes,
+/// plus any error surfaced while decoding the JWT.
+pub fn extract_claims_detached(module_bytes: impl AsRef<[u8]>, jwt: &str) -> Result<Token> {
+    let claims = Claims::decode(jwt)?;
+    let module: Module = deserialize_buffer(module_bytes.as_ref())?;
+
+    if !hash_matches(&module, &claims)? {
+        Err(errors::new(ErrorKind::InvalidModuleHash))
+    } else {
+        build_token(jwt.to_string(), claims)
+    }
+}
+
+/// The detached counterpart to [`sign_buffer_with_claims`]: builds claims from the supplied key
+/// pairs and returns a signed JWT that verifies against `buf` without embedding anything in it.
+pub fn sign_buffer_with_claims_detached(
+    buf: impl AsRef<[u8]>,
+    mod_kp: KeyPair,
+    acct_kp: KeyPair,
+    expires_in_days: Option<u64>,
+    not_before_days: Option<u64>,
+    caps: Vec<String>,
+    tags: Vec<String>,
+) -> Result<String> {
+    let claims = Claims::with_dates(
+        acct_kp.public_key(),
+        mod_kp.public_key(),
+        Some(caps),
+        Some(tags),
+        days_from_now_to_jwt_time(not_before_days),
+        days_from_now_to_jwt_time(expires_in_days),
+    );
+    embed_claims_detached(buf.as_ref(), &claims, &acct_kp)
+}
+
 pub fn sign_buffer_with_claims(
     buf: impl AsRef<[u8]>,
     mod_kp: KeyPair,
@@ -121,15 +587,87 @@ fn sha256_digest<R: Read>(mut reader: R) -> Result<Digest> {
     Ok(context.finish())
 }
 
-fn compute_hash_without_jwt(module: Module) -> Result<String> {
-    let mut refmod = module.clone();
-    refmod.clear_custom_section("jwt");
-    let modbytes = serialize(refmod)?;
+/// Returns `true` for the name of any section in the `jwt` family (`jwt`, `jwt.1`, ...). All of
+/// these are stripped before hashing so the `module_hash` is stable no matter how many signatures
+/// are stacked onto the module.
+fn is_jwt_section(name: &str) -> bool {
+    name == "jwt" || name.starts_with("jwt.")
+}
+
+/// The numeric index of a `jwt`-family section: bare `jwt` is 0, `jwt.N` is `N`. Used to order
+/// stacked signatures as `jwt`, `jwt.1`, `jwt.2`, ... (not the lexical order that would place
+/// `jwt.10` before `jwt.2`). A malformed suffix sorts last.
+fn jwt_section_index(name: &str) -> u64 {
+    if name == "jwt" {
+        0
+    } else {
+        name.strip_prefix("jwt.")
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(u64::MAX)
+    }
+}
+
+/// Computes the `module_hash` under canonicalization version `version`. Version `1` drops all
+/// `jwt`-family sections, serializes the module with customs removed for a padding-free base, then
+/// re-appends the remaining custom sections sorted by name and length-prefixed (so serializer
+/// reordering and name/data padding cannot perturb the digest). Version `0` is the legacy scheme:
+/// a plain SHA-256 over the module with only the exact `jwt` section cleared. Because both
+/// [`embed_claims`] and extraction hash the same stream for a given version, the embedded hash
+/// matches the extracted one exactly.
+fn compute_module_hash(module: &Module, version: u32) -> Result<String> {
+    if version == LEGACY_CANON_VERSION {
+        let mut refmod = module.clone();
+        refmod.clear_custom_section("jwt");
+        let modbytes = serialize(refmod)?;
+        let digest = sha256_digest(modbytes.as_slice())?;
+        return Ok(HEXUPPER.encode(digest.as_ref()));
+    }
+
+    // Serialize the module with *all* custom sections removed to get a stable, padding-free base.
+    let mut base = module.clone();
+    let all_customs: Vec<String> = base
+        .custom_sections()
+        .map(|sect| sect.name().to_string())
+        .collect();
+    for name in all_customs {
+        base.clear_custom_section(&name);
+    }
+    let mut stream = serialize(base)?;
+
+    // Re-append the non-jwt custom sections in a deterministic order, length-prefixed.
+    let mut customs: Vec<(&str, &[u8])> = module
+        .custom_sections()
+        .filter(|sect| !is_jwt_section(sect.name()))
+        .map(|sect| (sect.name(), sect.payload()))
+        .collect();
+    customs.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (name, payload) in customs {
+        stream.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        stream.extend_from_slice(name.as_bytes());
+        stream.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        stream.extend_from_slice(payload);
+    }
 
-    let digest = sha256_digest(modbytes.as_slice())?;
+    let digest = sha256_digest(stream.as_slice())?;
     Ok(HEXUPPER.encode(digest.as_ref()))
 }
 
+/// Computes the current-version canonical hash. Callers embedding a fresh token use this and
+/// record [`CANON_VERSION`] in the claims.
+fn compute_hash_without_jwt(module: Module) -> Result<String> {
+    compute_module_hash(&module, CANON_VERSION)
+}
+
+/// Returns `true` if the token's `module_hash` matches the module hash under the canonicalization version the
+/// token declares. A missing `canon_version` is treated as the legacy scheme, so pre-versioning
+/// modules still verify while a future `CANON_VERSION` can coexist deterministically — the path
+/// is selected by what the token says, not by trial hashing.
+fn hash_matches(module: &Module, claims: &Claims) -> Result<bool> {
+    let version = claims.canon_version.unwrap_or(LEGACY_CANON_VERSION);
+    Ok(compute_module_hash(module, version)? == claims.module_hash)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -144,6 +682,36 @@ mod test {
          AAAArpgICAAAPBgICAAAECfwJ/IABBAEoEQEEAIQIFIAAPCwNAIAEgAmoiAywAAEHpAEYEQCADQfkAOgA\
          ACyACQQFqIgIgAEcNAAsgAAsLg4CAgAAAAQuVgICAAAACQCMAJAIjAkGAgMACaiQDEAELCw==";
 
+    // Decodes the embedded fixture and round-trips it through the serializer, matching what the
+    // module loader would produce on disk.
+    fn fixture_module() -> Vec<u8> {
+        let dec_module = decode(WASM_BASE64).unwrap();
+        let m: Module = deserialize_buffer(&dec_module).unwrap();
+        serialize(m).unwrap()
+    }
+
+    // A set of claims with the given time window and capabilities, issued by `kp`.
+    fn test_claims(
+        kp: &KeyPair,
+        not_before: Option<u64>,
+        expires: Option<u64>,
+        caps: Vec<String>,
+    ) -> Claims {
+        Claims {
+            module_hash: "".to_string(),
+            expires,
+            id: nuid::next(),
+            issued_at: 0,
+            issuer: kp.public_key(),
+            subject: "test.wasm".to_string(),
+            not_before,
+            tags: None,
+            caps: Some(caps),
+            canon_version: None,
+            vc: None,
+        }
+    }
+
     #[test]
     fn claims_roundtrip() {
         // Serialize and de-serialize this because the module loader adds bytes to
@@ -163,7 +731,11 @@ mod test {
             not_before: None,
             tags: None,
             caps: Some(vec![MESSAGING.to_string(), KEY_VALUE.to_string()]),
+            canon_version: None,
+            vc: None,
         };
+        let embedded_hash =
+            compute_hash_without_jwt(deserialize_buffer(&raw_module).unwrap()).unwrap();
         let modified_bytecode = embed_claims(&raw_module, &claims, &kp).unwrap();
         println!(
             "Added {} bytes in custom section.",
@@ -172,9 +744,144 @@ mod test {
         if let Some(token) = extract_claims(&modified_bytecode).unwrap() {
             assert_eq!(claims.issuer, token.claims.issuer);
             assert_eq!(claims.caps, token.claims.caps);
-            assert_ne!(claims.module_hash, token.claims.module_hash);
+            // Canonical hashing is now stable, so the extracted hash matches the embedded one.
+            assert_eq!(token.claims.module_hash, embedded_hash);
         } else {
             assert!(false);
         }
     }
+
+    #[test]
+    fn rejects_expired_token() {
+        let kp = KeyPair::new_account();
+        let now = since_the_epoch().as_secs();
+        let claims = test_claims(&kp, None, Some(now - 10), vec![MESSAGING.to_string()]);
+        let err = validate_claims(&claims, &Validation::new()).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::TokenExpired));
+    }
+
+    #[test]
+    fn rejects_not_yet_valid_token() {
+        let kp = KeyPair::new_account();
+        let now = since_the_epoch().as_secs();
+        let claims = test_claims(&kp, Some(now + 1000), None, vec![MESSAGING.to_string()]);
+        let err = validate_claims(&claims, &Validation::new()).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::TokenNotValidYet));
+    }
+
+    #[test]
+    fn rejects_missing_required_capability() {
+        let kp = KeyPair::new_account();
+        let claims = test_claims(&kp, None, None, vec![MESSAGING.to_string()]);
+        let validation = Validation::new().with_required_caps(vec![KEY_VALUE.to_string()]);
+        let err = validate_claims(&claims, &validation).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::MissingCapability));
+    }
+
+    #[test]
+    fn rejects_missing_required_tag() {
+        let kp = KeyPair::new_account();
+        let mut claims = test_claims(&kp, None, None, vec![MESSAGING.to_string()]);
+        claims.tags = Some(vec!["beta".to_string()]);
+        let validation = Validation::new().with_required_tags(vec!["production".to_string()]);
+        let err = validate_claims(&claims, &validation).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::MissingTag));
+    }
+
+    #[test]
+    fn accepts_token_within_leeway_and_caps() {
+        let kp = KeyPair::new_account();
+        let now = since_the_epoch().as_secs();
+        // Expired two seconds ago, but tolerated by a five-second leeway.
+        let claims = test_claims(&kp, None, Some(now - 2), vec![KEY_VALUE.to_string()]);
+        let mut validation = Validation::new().with_required_caps(vec![KEY_VALUE.to_string()]);
+        validation.leeway = 5;
+        assert!(validate_claims(&claims, &validation).is_ok());
+    }
+
+    #[test]
+    fn detached_sign_and_extract_roundtrip() {
+        let raw_module = fixture_module();
+        let kp = KeyPair::new_account();
+        let claims = test_claims(&kp, None, None, vec![MESSAGING.to_string()]);
+
+        let jwt = embed_claims_detached(&raw_module, &claims, &kp).unwrap();
+        // The module is shipped untouched alongside the sidecar token.
+        let token = extract_claims_detached(&raw_module, &jwt).unwrap();
+        assert_eq!(token.claims.issuer, kp.public_key());
+
+        // Tampering with the module (adding a non-jwt custom section) breaks verification.
+        let mut tampered: Module = deserialize_buffer(&raw_module).unwrap();
+        tampered.set_custom_section("tamper", b"evil".to_vec());
+        let mut tampered_bytes = Vec::new();
+        tampered.serialize(&mut tampered_bytes).unwrap();
+        let err = extract_claims_detached(&tampered_bytes, &jwt).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidModuleHash));
+    }
+
+    #[test]
+    fn stacked_signatures_all_validate() {
+        let raw_module = fixture_module();
+        let acct_kp = KeyPair::new_account();
+        let auditor_kp = KeyPair::new_account();
+
+        let acct_claims = test_claims(&acct_kp, None, None, vec![MESSAGING.to_string()]);
+        let signed = embed_claims(&raw_module, &acct_claims, &acct_kp).unwrap();
+
+        let auditor_claims = test_claims(&auditor_kp, None, None, vec![KEY_VALUE.to_string()]);
+        let counter_signed =
+            embed_additional_claims(&signed, &auditor_claims, &auditor_kp).unwrap();
+
+        let tokens = extract_all_claims(&counter_signed).unwrap();
+        assert_eq!(tokens.len(), 2);
+
+        // Both signatures validate against the same jwt-family-stripped hash.
+        let issuers: Vec<&str> = tokens.iter().map(|t| t.claims.issuer.as_str()).collect();
+        assert!(issuers.contains(&acct_kp.public_key().as_str()));
+        assert!(issuers.contains(&auditor_kp.public_key().as_str()));
+        assert!(tokens
+            .iter()
+            .all(|t| t.claims.module_hash == tokens[0].claims.module_hash));
+    }
+
+    #[test]
+    fn callback_signer_and_pinned_verifier() {
+        let raw_module = fixture_module();
+        let kp = KeyPair::new_account();
+        let claims = test_claims(&kp, None, None, vec![MESSAGING.to_string()]);
+
+        // Sign via a callback signer rather than handing the embed path a KeyPair directly.
+        let signer = CallbackSigner::new(kp.public_key(), |c: &Claims| c.encode(&kp));
+        let bytes = embed_claims_with_signer(&raw_module, &claims, &signer).unwrap();
+
+        // A verifier pinned to the issuer accepts the token.
+        let verifier = PublicKeyVerifier::new(kp.public_key());
+        let token = extract_claims_with_verifier(&bytes, &verifier)
+            .unwrap()
+            .unwrap();
+        assert_eq!(token.claims.issuer, kp.public_key());
+
+        // A verifier pinned to a different key rejects it.
+        let wrong = PublicKeyVerifier::new(KeyPair::new_account().public_key());
+        let err = extract_claims_with_verifier(&bytes, &wrong).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::InvalidSignature));
+    }
+
+    #[test]
+    fn verifiable_credential_roundtrip() {
+        let raw_module = fixture_module();
+        let kp = KeyPair::new_account();
+        let claims = test_claims(&kp, None, None, vec![MESSAGING.to_string()]);
+        let credential = VerifiableCredential::new(
+            kp.public_key(),
+            vec!["ModuleProvenance".to_string()],
+            serde_json::json!({ "id": "test.wasm", "audited": true }),
+        );
+
+        let modified_bytecode =
+            embed_claims_with_credential(&raw_module, &claims, &credential, &kp).unwrap();
+        let token = extract_claims(&modified_bytecode).unwrap().unwrap();
+
+        assert_eq!(token.vc, Some(credential));
+    }
 }